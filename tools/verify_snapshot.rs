@@ -1,5 +1,7 @@
 // Utility to inspect and verify a ShadowVault snapshot metadata JSON file.
-// Validates the Ed25519 signature, summarizes contents, and checks local chunk availability.
+// Validates the Ed25519 signature(s), summarizes contents, and checks local chunk availability.
+// Supports threshold (M-of-N) keysets: a snapshot is accepted when at least `threshold`
+// distinct authorized keys have signed the canonical payload.
 // Build with:
 //   cargo install --path .   # or compile standalone with `rustc` after adding dependencies manually
 //
@@ -11,37 +13,84 @@
 // ed25519-dalek = { version = "1.0", features = ["std"] }
 // clap = { version = "4.2", features = ["derive"] }
 // humantime = "2.1"
+// blake3 = "1"
+// sha2 = "0.10"
+// hex = "0.4"
+// zstd = "0.13"
 
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::collections::HashSet;
+use std::time::SystemTime;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::Deserialize;
 use base64::{engine::general_purpose, Engine as _};
 use ed25519_dalek::{PublicKey, Signature, Verifier};
 use std::fmt::Write as FmtWrite;
+use std::io::Write as IoWrite;
 use std::fs;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Verify ShadowVault snapshot metadata and local chunk availability")]
 struct Args {
-    /// Path to snapshot metadata JSON (decrypted)
+    /// Path to snapshot metadata JSON (decrypted); required except in `stats` mode
     #[arg(short, long)]
-    snapshot: PathBuf,
+    snapshot: Option<PathBuf>,
 
     /// Base object storage directory where chunks live
     #[arg(short, long)]
     objects: PathBuf,
 
-    /// Optionally override signer public key (base64) instead of using embedded signer_pub
+    /// Optionally override signer public key (base64) instead of using embedded signer_pub.
+    /// Also acts as an authorized trust anchor for threshold keysets.
     #[arg(long)]
     pubkey: Option<String>,
 
+    /// File of authorized public keys (one per line, "key_id base64" or just "base64";
+    /// `#` comments allowed). When given, only signatures from these keys count toward
+    /// the threshold — the embedded `signers` array alone is not trusted.
+    #[arg(long)]
+    authorized_keys: Option<PathBuf>,
+
     /// Maximum number of missing chunk hashes to display
     #[arg(long, default_value_t = 20)]
     show_missing: usize,
+
+    /// Reject the snapshot if its timestamp is older than this age (e.g. "30d", "12h")
+    #[arg(long)]
+    max_age: Option<String>,
+
+    /// Directory of snapshot files (<id>.json) used to walk and validate the parent chain
+    #[arg(long)]
+    chain: Option<PathBuf>,
+
+    /// Recompute each chunk's digest and compare it to the referenced hash
+    #[arg(long)]
+    verify_content: bool,
+
+    /// Force the chunk hash algorithm (blake3, sha256, sha512); auto-detected from length otherwise
+    #[arg(long)]
+    hash_algo: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Rebuild the snapshot's files from chunks into an output directory
+    Restore {
+        /// Output directory to reconstruct the snapshot into (must not already exist)
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Report cross-snapshot deduplication and storage statistics
+    Stats {
+        /// Snapshot metadata files to analyze, oldest first (e.g. a backup history)
+        snapshots: Vec<PathBuf>,
+    },
 }
 
 #[derive(Deserialize)]
@@ -53,20 +102,53 @@ struct FileEntry {
     chunk_hashes: Vec<String>,
 }
 
+/// One member of a threshold keyset: the custodian's key id, its Ed25519 public
+/// key (base64), and a detached signature (base64) over the canonical bytes.
+#[derive(Deserialize)]
+struct SignerEntry {
+    key_id: String,
+    signer_pub: String,
+    signature: String,
+}
+
 #[derive(Deserialize)]
 struct SnapshotMetadata {
     id: String,
     parent: Option<String>,
     timestamp: String,
     root: String,
+    // Optional freshness fields. `expires` is an RFC3339 instant past which the
+    // snapshot is rejected; `version` is a monotonic counter used for rollback
+    // detection across the parent chain.
+    #[serde(default)]
+    expires: Option<String>,
+    #[serde(default)]
+    version: Option<u64>,
     files: Vec<FileEntry>,
-    signer_pub: String,
-    signature: String,
+    // Legacy single-signer fields. Kept for backwards compatibility with
+    // snapshots written before threshold keysets existed.
+    #[serde(default)]
+    signer_pub: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    // Threshold keyset. When `signers` is non-empty the snapshot is verified
+    // against `threshold` distinct valid signatures rather than a single key.
+    #[serde(default)]
+    signers: Vec<SignerEntry>,
+    #[serde(default)]
+    threshold: u32,
 }
 
-fn canonical_snapshot_bytes(snap: &SnapshotMetadata) -> Vec<u8> {
+fn canonical_snapshot_bytes(snap: &SnapshotMetadata, signer_pub: Option<&str>) -> Vec<u8> {
     // Manually assemble JSON with deterministic ordering matching Go's json.Marshal of struct:
-    // fields order: id, parent, timestamp, root, files, signer_pub
+    // fields order: id, parent, timestamp, root, expires, version, files, signer_pub
+    // (expires and version only when present; signer_pub only when passed in).
+    //
+    // For threshold keysets (`signer_pub` is None) every signature-bearing field is
+    // excluded so each custodian signs identical bytes. The legacy single-signer
+    // format signed a payload that *included* `signer_pub`, so that path passes it
+    // in to reproduce the exact bytes the original signer used — preserving
+    // backwards compatibility with snapshots written before threshold keysets.
     let mut s = String::new();
     s.push('{');
 
@@ -86,6 +168,14 @@ fn canonical_snapshot_bytes(snap: &SnapshotMetadata) -> Vec<u8> {
     // "root"
     write!(s, ",\"root\":{}", serde_json::to_string(&snap.root).unwrap()).unwrap();
 
+    // "expires" / "version" — omitted when absent (Go `omitempty` semantics).
+    if let Some(ref e) = snap.expires {
+        write!(s, ",\"expires\":{}", serde_json::to_string(e).unwrap()).unwrap();
+    }
+    if let Some(v) = snap.version {
+        write!(s, ",\"version\":{}", v).unwrap();
+    }
+
     // "files"
     s.push_str(",\"files\":[");
     let mut first_file = true;
@@ -115,36 +205,758 @@ fn canonical_snapshot_bytes(snap: &SnapshotMetadata) -> Vec<u8> {
     }
     s.push(']');
 
-    // "signer_pub"
-    write!(s, ",\"signer_pub\":{}", serde_json::to_string(&snap.signer_pub).unwrap()).unwrap();
+    // "signer_pub" — legacy single-signer payloads were signed including this field.
+    if let Some(pk) = signer_pub {
+        write!(s, ",\"signer_pub\":{}", serde_json::to_string(pk).unwrap()).unwrap();
+    }
 
     s.push('}');
     s.into_bytes()
 }
 
-fn chunk_exists(base: &Path, hash: &str) -> bool {
-    // try direct
+/// Decode a base64 Ed25519 public key and verify a detached base64 signature
+/// over `canonical`. Returns Ok(()) only when the signature is valid.
+fn verify_detached(signer_pub_b64: &str, signature_b64: &str, canonical: &[u8]) -> anyhow::Result<()> {
+    let pub_bytes = general_purpose::STANDARD.decode(signer_pub_b64)
+        .map_err(|e| anyhow::anyhow!("failed to decode signer_pub base64: {}", e))?;
+    let public_key = PublicKey::from_bytes(&pub_bytes)
+        .map_err(|e| anyhow::anyhow!("invalid ed25519 public key: {}", e))?;
+    let sig_bytes = general_purpose::STANDARD.decode(signature_b64)
+        .map_err(|e| anyhow::anyhow!("failed to decode signature base64: {}", e))?;
+    let signature = Signature::from_bytes(&sig_bytes)
+        .map_err(|e| anyhow::anyhow!("invalid signature format: {}", e))?;
+    public_key.verify(canonical, &signature)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// A trust anchor: public keys authorized to sign snapshots, optionally bound to
+/// a specific `key_id` (role). Built from `--pubkey` and `--authorized-keys`.
+#[derive(Default)]
+struct AuthorizedKeys {
+    // (optional key_id/role binding, raw public key bytes)
+    entries: Vec<(Option<String>, Vec<u8>)>,
+}
+
+impl AuthorizedKeys {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `pub_bytes` is authorized, honoring a role binding when present.
+    fn authorizes(&self, key_id: &str, pub_bytes: &[u8]) -> bool {
+        self.entries.iter().any(|(role, pk)| {
+            pk == pub_bytes && role.as_deref().is_none_or(|r| r == key_id)
+        })
+    }
+
+    /// Load authorized keys from an optional `--pubkey` and an optional file of
+    /// "key_id base64" / "base64" lines (`#` comments and blanks ignored).
+    fn load(pubkey: Option<&str>, file: Option<&Path>) -> anyhow::Result<AuthorizedKeys> {
+        let mut entries = Vec::new();
+        if let Some(pk) = pubkey {
+            let bytes = general_purpose::STANDARD.decode(pk)
+                .map_err(|e| anyhow::anyhow!("failed to decode --pubkey base64: {}", e))?;
+            entries.push((None, bytes));
+        }
+        if let Some(path) = file {
+            let raw = fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("failed to read authorized keys {}: {}", path.display(), e))?;
+            for line in raw.lines() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let mut parts = line.split_whitespace();
+                let (role, b64) = match (parts.next(), parts.next()) {
+                    (Some(a), Some(b)) => (Some(a.to_string()), b),
+                    (Some(a), None) => (None, a),
+                    _ => continue,
+                };
+                let bytes = general_purpose::STANDARD.decode(b64)
+                    .map_err(|e| anyhow::anyhow!("invalid base64 in {}: {}", path.display(), e))?;
+                entries.push((role, bytes));
+            }
+        }
+        Ok(AuthorizedKeys { entries })
+    }
+}
+
+/// One signer's verification result, kept pure so counting can be unit-tested.
+struct SignerCheck {
+    key_id: String,
+    pub_bytes: Vec<u8>,
+    valid: bool,
+    authorized: bool,
+}
+
+/// Count distinct accepted signers from a set of verification results. A signer
+/// is accepted when its signature is valid, its key is authorized, and neither
+/// its `key_id` nor its public key has already been counted. Returns the accepted
+/// key ids and the rejected ones (invalid, unauthorized, or duplicate).
+fn count_distinct_authorized(checks: &[SignerCheck]) -> (Vec<String>, Vec<String>) {
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    let mut seen_ids = HashSet::new();
+    let mut seen_keys: HashSet<Vec<u8>> = HashSet::new();
+    for c in checks {
+        if !c.valid || !c.authorized {
+            rejected.push(c.key_id.clone());
+            continue;
+        }
+        let fresh_id = !seen_ids.contains(&c.key_id);
+        let fresh_key = !seen_keys.contains(&c.pub_bytes);
+        if fresh_id && fresh_key {
+            seen_ids.insert(c.key_id.clone());
+            seen_keys.insert(c.pub_bytes.clone());
+            accepted.push(c.key_id.clone());
+        } else {
+            // Valid and authorized, but a duplicate of an already-counted signer.
+            rejected.push(c.key_id.clone());
+        }
+    }
+    (accepted, rejected)
+}
+
+/// Verify a snapshot's signatures.
+///
+/// When the snapshot carries a `signers` keyset, each listed key is verified and
+/// distinct valid signatures are counted against `threshold`. Distinctness is
+/// enforced on both the `key_id` and the decoded public key, so one custodian
+/// cannot be counted twice by listing the same key under several ids. When an
+/// authorized keyset (`--pubkey`/`--authorized-keys`) is supplied it acts as the
+/// trust anchor: only signatures from authorized keys count, so an attacker who
+/// rewrites `signers`/`threshold` with their own keys is rejected. Otherwise the
+/// legacy single `signer_pub`/`signature` pair is used (with the signed payload
+/// including `signer_pub` for backwards compatibility).
+fn verify_signatures(snap: &SnapshotMetadata, pubkey_override: Option<&str>, authorized: &AuthorizedKeys) -> anyhow::Result<()> {
+    if !snap.signers.is_empty() {
+        let canonical = canonical_snapshot_bytes(snap, None);
+        let threshold = snap.threshold.max(1);
+        if authorized.is_empty() {
+            println!("WARNING: no --pubkey/--authorized-keys trust anchor; \
+                trusting only the keys embedded in the snapshot");
+        }
+        let checks: Vec<SignerCheck> = snap.signers.iter().map(|entry| {
+            let pub_bytes = general_purpose::STANDARD.decode(&entry.signer_pub).unwrap_or_default();
+            let valid = verify_detached(&entry.signer_pub, &entry.signature, &canonical).is_ok();
+            // With no trust anchor, fall back to trusting embedded keys (legacy behavior).
+            let authorized_key = authorized.is_empty() || authorized.authorizes(&entry.key_id, &pub_bytes);
+            SignerCheck { key_id: entry.key_id.clone(), pub_bytes, valid, authorized: authorized_key }
+        }).collect();
+
+        let (signed, rejected) = count_distinct_authorized(&checks);
+        println!("Signed by ({}/{}): {}", signed.len(), threshold,
+            if signed.is_empty() { "<none>".to_string() } else { signed.join(", ") });
+        if !rejected.is_empty() {
+            println!("Rejected signatures (invalid/unauthorized/duplicate): {}", rejected.join(", "));
+        }
+        if (signed.len() as u32) < threshold {
+            println!("Signature: INVALID (threshold not met)");
+            return Err(anyhow::anyhow!(
+                "threshold not met: {} of {} required signatures present", signed.len(), threshold));
+        }
+        println!("Signature: valid ({}-of-{} threshold satisfied)", threshold, snap.signers.len());
+        return Ok(());
+    }
+
+    // Legacy single-signer path. The original signer signed over the embedded
+    // `signer_pub`, so reproduce those exact bytes here.
+    let signer_pub = pubkey_override
+        .or(snap.signer_pub.as_deref())
+        .ok_or_else(|| anyhow::anyhow!("snapshot has no signer_pub and none was provided"))?;
+    let signature = snap.signature.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("snapshot has no signature"))?;
+    let canonical = canonical_snapshot_bytes(snap, Some(snap.signer_pub.as_deref().unwrap_or(signer_pub)));
+    match verify_detached(signer_pub, signature, &canonical) {
+        Ok(_) => {
+            println!("Signature: valid");
+            Ok(())
+        }
+        Err(e) => {
+            println!("Signature: INVALID ({})", e);
+            Err(anyhow::anyhow!("signature verification failed"))
+        }
+    }
+}
+
+/// Parse an RFC3339 timestamp into a `SystemTime`, attributing errors to `field`.
+fn parse_ts(field: &str, value: &str) -> anyhow::Result<SystemTime> {
+    humantime::parse_rfc3339(value)
+        .map_err(|e| anyhow::anyhow!("failed to parse {} timestamp {:?}: {}", field, value, e))
+}
+
+/// Enforce freshness: reject a snapshot that has passed its `expires` instant or
+/// whose age relative to `timestamp` exceeds `--max-age`.
+fn check_freshness(snap: &SnapshotMetadata, max_age: Option<&str>) -> anyhow::Result<()> {
+    let now = SystemTime::now();
+
+    if let Some(ref expires) = snap.expires {
+        let exp = parse_ts("expires", expires)?;
+        if now > exp {
+            return Err(anyhow::anyhow!("snapshot expired at {}", expires));
+        }
+        println!("Expires: {} (not yet expired)", expires);
+    }
+
+    if let Some(spec) = max_age {
+        let max = humantime::parse_duration(spec)
+            .map_err(|e| anyhow::anyhow!("invalid --max-age {:?}: {}", spec, e))?;
+        let ts = parse_ts("timestamp", &snap.timestamp)?;
+        match now.duration_since(ts) {
+            Ok(age) if age > max => {
+                return Err(anyhow::anyhow!(
+                    "snapshot age {} exceeds --max-age {}",
+                    humantime::format_duration(age), humantime::format_duration(max)));
+            }
+            _ => println!("Age within --max-age {}", humantime::format_duration(max)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Walk the parent lineage of `snap`, loading each ancestor from `<dir>/<id>.json`,
+/// and enforce anti-rollback monotonicity: every child's `timestamp` must be
+/// strictly greater than its parent's and its optional `version` non-decreasing.
+fn verify_chain(dir: &Path, snap: &SnapshotMetadata) -> anyhow::Result<()> {
+    let mut depth = 0usize;
+    let mut child = snap_view(snap);
+    // Track ids already on the path so an attacker-supplied parent cycle
+    // (a→b→a, or a self-parent) is rejected instead of looping forever.
+    let mut visited = HashSet::new();
+    visited.insert(child.id.clone());
+    while let Some(parent_id) = child.parent.clone() {
+        if !visited.insert(parent_id.clone()) {
+            return Err(anyhow::anyhow!(
+                "parent chain cycle detected at {} (revisited from {})", parent_id, child.id));
+        }
+        let path = dir.join(format!("{}.json", parent_id));
+        let raw = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("failed to read parent snapshot {}: {}", path.display(), e))?;
+        let parent: SnapshotMetadata = serde_json::from_str(&raw)
+            .map_err(|e| anyhow::anyhow!("failed to parse parent snapshot {}: {}", path.display(), e))?;
+        let parent = snap_view(&parent);
+
+        check_chain_link(&child, &parent)?;
+
+        depth += 1;
+        child = parent;
+    }
+    println!("Chain: verified {} parent link(s)", depth);
+    Ok(())
+}
+
+/// Enforce anti-rollback monotonicity for a single child→parent link: the child's
+/// timestamp must be strictly newer than the parent's, and its optional `version`
+/// non-decreasing relative to the parent.
+fn check_chain_link(child: &ChainView, parent: &ChainView) -> anyhow::Result<()> {
+    let child_ts = parse_ts("timestamp", &child.timestamp)?;
+    let parent_ts = parse_ts("timestamp", &parent.timestamp)?;
+    if child_ts <= parent_ts {
+        return Err(anyhow::anyhow!(
+            "rollback detected: {} timestamp {} is not newer than parent {} timestamp {}",
+            child.id, child.timestamp, parent.id, parent.timestamp));
+    }
+    if let (Some(cv), Some(pv)) = (child.version, parent.version) {
+        if cv < pv {
+            return Err(anyhow::anyhow!(
+                "rollback detected: {} version {} is lower than parent {} version {}",
+                child.id, cv, parent.id, pv));
+        }
+    }
+    Ok(())
+}
+
+/// Minimal owned view of a snapshot used while walking the parent chain.
+struct ChainView {
+    id: String,
+    parent: Option<String>,
+    timestamp: String,
+    version: Option<u64>,
+}
+
+fn snap_view(snap: &SnapshotMetadata) -> ChainView {
+    ChainView {
+        id: snap.id.clone(),
+        parent: snap.parent.clone(),
+        timestamp: snap.timestamp.clone(),
+        version: snap.version,
+    }
+}
+
+/// Resolve the on-disk path of a chunk, handling both the flat `<hash>` layout
+/// and the two-level `<first2>/<rest>` fan-out. Returns None if neither exists.
+fn chunk_path(base: &Path, hash: &str) -> Option<PathBuf> {
     let direct = base.join(hash);
     if direct.exists() {
-        return true;
+        return Some(direct);
     }
-    // try two-level split as <first2>/<rest>
     if hash.len() > 2 {
-        let prefix = &hash[0..2];
-        let rest = &hash[2..];
-        let two = base.join(prefix).join(rest);
+        let two = base.join(&hash[0..2]).join(&hash[2..]);
         if two.exists() {
-            return true;
+            return Some(two);
+        }
+    }
+    None
+}
+
+/// Backend for reading chunk objects. Loose directories and packed archives both
+/// implement this so verification and restore are agnostic to on-disk layout.
+trait ObjectStore {
+    /// Whether a chunk with this hash is available.
+    fn exists(&self, hash: &str) -> bool;
+    /// Open a streaming reader over a chunk's contents so callers can process it
+    /// in fixed-size buffers. Loose files and plain packs are read from disk as
+    /// the reader is consumed; a zstd pack is decompressed into memory first.
+    fn chunk_reader(&self, hash: &str) -> anyhow::Result<Box<dyn Read>>;
+    /// On-disk size of a chunk in bytes, or None if it is absent.
+    fn physical_size(&self, hash: &str) -> Option<u64>;
+    /// Whether `physical_size` reflects actual on-disk bytes. False for compressed
+    /// packs, where it is a logical (uncompressed) estimate.
+    fn physical_size_is_exact(&self) -> bool {
+        true
+    }
+}
+
+/// Chunks stored as individual files under a directory, in either the flat or
+/// two-level `<first2>/<rest>` layout.
+struct LooseStore {
+    base: PathBuf,
+}
+
+impl ObjectStore for LooseStore {
+    fn exists(&self, hash: &str) -> bool {
+        chunk_path(&self.base, hash).is_some()
+    }
+
+    fn chunk_reader(&self, hash: &str) -> anyhow::Result<Box<dyn Read>> {
+        let path = chunk_path(&self.base, hash)
+            .ok_or_else(|| anyhow::anyhow!("missing chunk {}", hash))?;
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn physical_size(&self, hash: &str) -> Option<u64> {
+        let path = chunk_path(&self.base, hash)?;
+        fs::metadata(path).ok().map(|m| m.len())
+    }
+}
+
+/// Byte range of a chunk within a pack's (decompressed) data stream.
+#[derive(Deserialize, Clone, Copy)]
+struct PackEntry {
+    offset: u64,
+    length: u64,
+}
+
+/// Backing bytes for a pack: a seekable uncompressed `.pack`, or a zstd-compressed
+/// pack whose decompressed stream is materialized lazily (and memoized) only when a
+/// chunk is actually read, so presence checks and stats never decompress it.
+enum PackData {
+    Plain(PathBuf),
+    Zstd {
+        path: PathBuf,
+        cache: std::cell::RefCell<Option<std::rc::Rc<Vec<u8>>>>,
+    },
+}
+
+impl PackData {
+    /// Return the decompressed stream, decompressing and caching on first use.
+    fn materialized(&self) -> anyhow::Result<std::rc::Rc<Vec<u8>>> {
+        match self {
+            PackData::Plain(_) => unreachable!("materialized() is only for compressed packs"),
+            PackData::Zstd { path, cache } => {
+                if let Some(bytes) = cache.borrow().as_ref() {
+                    return Ok(bytes.clone());
+                }
+                let compressed = File::open(path)
+                    .map_err(|e| anyhow::anyhow!("failed to open pack {}: {}", path.display(), e))?;
+                let bytes = zstd::stream::decode_all(BufReader::new(compressed))
+                    .map_err(|e| anyhow::anyhow!("failed to decompress pack {}: {}", path.display(), e))?;
+                let rc = std::rc::Rc::new(bytes);
+                *cache.borrow_mut() = Some(rc.clone());
+                Ok(rc)
+            }
+        }
+    }
+}
+
+/// Chunks stored in a single packed archive plus a sidecar index
+/// (`<pack>.idx`, JSON mapping chunk hash → {offset, length}).
+struct PackStore {
+    index: std::collections::HashMap<String, PackEntry>,
+    data: PackData,
+}
+
+impl PackStore {
+    fn open(path: &Path) -> anyhow::Result<PackStore> {
+        let idx_path = PathBuf::from(format!("{}.idx", path.display()));
+        let raw = fs::read_to_string(&idx_path)
+            .map_err(|e| anyhow::anyhow!("failed to read pack index {}: {}", idx_path.display(), e))?;
+        let index: std::collections::HashMap<String, PackEntry> = serde_json::from_str(&raw)
+            .map_err(|e| anyhow::anyhow!("failed to parse pack index {}: {}", idx_path.display(), e))?;
+
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let data = if name.ends_with(".tar.zst") || name.ends_with(".zst") {
+            // Decompression is deferred until the first chunk_reader() call.
+            PackData::Zstd { path: path.to_path_buf(), cache: std::cell::RefCell::new(None) }
+        } else {
+            // Verify the pack is openable up front, but read ranges lazily.
+            File::open(path)
+                .map_err(|e| anyhow::anyhow!("failed to open pack {}: {}", path.display(), e))?;
+            PackData::Plain(path.to_path_buf())
+        };
+        Ok(PackStore { index, data })
+    }
+}
+
+impl ObjectStore for PackStore {
+    fn exists(&self, hash: &str) -> bool {
+        self.index.contains_key(hash)
+    }
+
+    fn chunk_reader(&self, hash: &str) -> anyhow::Result<Box<dyn Read>> {
+        use std::io::{Seek, SeekFrom};
+        let entry = self.index.get(hash)
+            .ok_or_else(|| anyhow::anyhow!("missing chunk {}", hash))?;
+        match &self.data {
+            PackData::Plain(path) => {
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(entry.offset))?;
+                Ok(Box::new(file.take(entry.length)))
+            }
+            PackData::Zstd { .. } => {
+                let bytes = self.data.materialized()?;
+                let start = entry.offset as usize;
+                let end = start + entry.length as usize;
+                let slice = bytes.get(start..end)
+                    .ok_or_else(|| anyhow::anyhow!("chunk {} range out of bounds in pack", hash))?;
+                Ok(Box::new(std::io::Cursor::new(slice.to_vec())))
+            }
+        }
+    }
+
+    fn physical_size(&self, hash: &str) -> Option<u64> {
+        // For a plain pack this is the exact on-disk byte range; for a compressed
+        // pack it is the logical (uncompressed) length — see physical_size_is_exact.
+        self.index.get(hash).map(|e| e.length)
+    }
+
+    fn physical_size_is_exact(&self) -> bool {
+        matches!(self.data, PackData::Plain(_))
+    }
+}
+
+/// Select a backend from `--objects`: a directory uses the loose layout, a
+/// `.pack`/`.tar.zst` file uses the packed layout.
+fn open_object_store(path: &Path) -> anyhow::Result<Box<dyn ObjectStore>> {
+    if path.is_dir() {
+        return Ok(Box::new(LooseStore { base: path.to_path_buf() }));
+    }
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if name.ends_with(".pack") || name.ends_with(".tar.zst") || name.ends_with(".zst") {
+        return Ok(Box::new(PackStore::open(path)?));
+    }
+    Err(anyhow::anyhow!(
+        "unsupported object store {}: expected a directory or a .pack/.tar.zst file", path.display()))
+}
+
+/// Chunk content hash algorithms ShadowVault may have used to name chunks.
+#[derive(Clone, Copy)]
+enum HashAlgo {
+    Blake3,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgo {
+    /// Resolve the algorithm from an explicit `--hash-algo` flag, falling back to
+    /// auto-detection from the hex hash length (64 hex / 32 bytes → BLAKE3,
+    /// 128 hex / 64 bytes → SHA-512).
+    fn resolve(flag: Option<&str>, hash: &str) -> anyhow::Result<HashAlgo> {
+        if let Some(name) = flag {
+            return match name.to_ascii_lowercase().as_str() {
+                "blake3" => Ok(HashAlgo::Blake3),
+                "sha256" | "sha-256" => Ok(HashAlgo::Sha256),
+                "sha512" | "sha-512" => Ok(HashAlgo::Sha512),
+                other => Err(anyhow::anyhow!("unknown --hash-algo {:?}", other)),
+            };
+        }
+        match hash.len() {
+            64 => Ok(HashAlgo::Blake3), // 32 bytes: BLAKE3 or SHA-256; default to BLAKE3
+            128 => Ok(HashAlgo::Sha512), // 64 bytes
+            n => Err(anyhow::anyhow!(
+                "cannot auto-detect hash algorithm from {}-char hash; pass --hash-algo", n)),
+        }
+    }
+}
+
+/// Incremental digest state for the supported algorithms.
+enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Hasher {
+        use sha2::Digest;
+        match algo {
+            HashAlgo::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgo::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgo::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        match self {
+            Hasher::Blake3(h) => { h.update(data); }
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
         }
     }
-    false
+
+    fn finalize_hex(self) -> String {
+        use sha2::Digest;
+        match self {
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+            Hasher::Sha256(h) => hex::encode(h.finalize()),
+            Hasher::Sha512(h) => hex::encode(h.finalize()),
+        }
+    }
+}
+
+/// Stream `reader` through fixed-size buffers, returning the lowercase hex digest
+/// under `algo`. When `sink` is provided, each buffer is also written to it, so a
+/// restore can hash and write a chunk in a single bounded-memory pass.
+fn hash_reader(mut reader: impl Read, algo: HashAlgo, mut sink: Option<&mut dyn IoWrite>) -> anyhow::Result<String> {
+    let mut hasher = Hasher::new(algo);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        if let Some(sink) = sink.as_deref_mut() {
+            sink.write_all(&buf[..n])?;
+        }
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// Windows reserved device names that must not appear as a path component, even
+/// with an extension (following TUF's target path rules).
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Validate a snapshot file path and turn it into a safe relative `PathBuf`.
+///
+/// Rejects absolute paths (including drive-letter paths such as `C:\x`), `..`
+/// traversal, control characters, and reserved Windows device names so a
+/// malicious snapshot cannot escape the restore root. Empty and `.` components
+/// are skipped; a path made up only of them is rejected.
+fn sanitize_rel_path(path: &str) -> anyhow::Result<PathBuf> {
+    if path.is_empty() {
+        return Err(anyhow::anyhow!("empty file path"));
+    }
+    // Normalize separators so Windows-style paths are checked component-wise too.
+    let normalized = path.replace('\\', "/");
+    let b = normalized.as_bytes();
+    let drive_prefix = b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':';
+    if normalized.starts_with('/') || drive_prefix {
+        return Err(anyhow::anyhow!("absolute path not allowed: {:?}", path));
+    }
+    let mut rel = PathBuf::new();
+    for comp in normalized.split('/') {
+        if comp.is_empty() || comp == "." {
+            continue;
+        }
+        if comp == ".." {
+            return Err(anyhow::anyhow!("path traversal not allowed: {:?}", path));
+        }
+        if comp.chars().any(|c| c.is_control()) {
+            return Err(anyhow::anyhow!("control character in path: {:?}", path));
+        }
+        // A reserved device name is matched against the component's stem,
+        // case-insensitively (e.g. "con.txt" is still reserved).
+        let stem = comp.split('.').next().unwrap_or(comp);
+        if RESERVED_WINDOWS_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            return Err(anyhow::anyhow!("reserved device name in path: {:?}", path));
+        }
+        rel.push(comp);
+    }
+    if rel.as_os_str().is_empty() {
+        return Err(anyhow::anyhow!("path resolves to empty: {:?}", path));
+    }
+    Ok(rel)
+}
+
+/// Reconstruct every file in `snap` under `out` by concatenating chunks in order.
+///
+/// The tree is assembled in a sibling temp directory and renamed into place only
+/// if every chunk is present and its content hash matches, so a failed restore
+/// never leaves a partial tree at `out`.
+fn restore(snap: &SnapshotMetadata, store: &dyn ObjectStore, out: &Path, hash_algo: Option<&str>) -> anyhow::Result<()> {
+    if out.exists() {
+        return Err(anyhow::anyhow!("output directory {} already exists", out.display()));
+    }
+    let staging = staging_dir(out);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).ok();
+    }
+    fs::create_dir_all(&staging)
+        .map_err(|e| anyhow::anyhow!("failed to create staging dir {}: {}", staging.display(), e))?;
+
+    let result = (|| -> anyhow::Result<()> {
+        for fe in &snap.files {
+            let rel = sanitize_rel_path(&fe.path)?;
+            let target = staging.join(&rel);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create(&target)
+                .map_err(|e| anyhow::anyhow!("failed to create {}: {}", target.display(), e))?;
+            for ch in &fe.chunk_hashes {
+                if !store.exists(ch) {
+                    return Err(anyhow::anyhow!("missing chunk {} for {}", ch, fe.path));
+                }
+                let algo = HashAlgo::resolve(hash_algo, ch)?;
+                // Stream the chunk into the file and hash it in one bounded pass;
+                // any partial write is discarded with the staging dir on abort.
+                let digest = hash_reader(store.chunk_reader(ch)?, algo, Some(&mut file as &mut dyn IoWrite))?;
+                if !digest.eq_ignore_ascii_case(ch) {
+                    return Err(anyhow::anyhow!("corrupted chunk {} for {}", ch, fe.path));
+                }
+            }
+            file.flush()?;
+            apply_metadata(&file, fe)?;
+            drop(file);
+            println!("restored {}", fe.path);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        fs::remove_dir_all(&staging).ok();
+        return Err(e);
+    }
+
+    fs::rename(&staging, out)
+        .map_err(|e| anyhow::anyhow!("failed to move staging into place: {}", e))?;
+    println!("Restore complete: {} file(s) -> {}", snap.files.len(), out.display());
+    Ok(())
+}
+
+/// Sibling staging directory for an atomic restore (`<out>.restore-tmp`).
+fn staging_dir(out: &Path) -> PathBuf {
+    let mut name = out.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".restore-tmp");
+    out.with_file_name(name)
+}
+
+/// Apply the stored mode and modification time to a freshly-written file.
+fn apply_metadata(file: &File, fe: &FileEntry) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Strip setuid/setgid/sticky bits so a hostile snapshot cannot plant a
+        // privileged binary under the output root; only the permission bits apply.
+        let mode = fe.mode & 0o0777;
+        file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = fe.mode;
+    }
+    if let Ok(mtime) = humantime::parse_rfc3339(&fe.mod_time) {
+        // Best effort: mtime restoration should not fail an otherwise-good restore.
+        let _ = file.set_modified(mtime);
+    }
+    Ok(())
+}
+
+/// Compute and print cross-snapshot deduplication and storage statistics.
+///
+/// A single map accumulates every chunk hash seen so far, so the first snapshot
+/// to reference a chunk is credited with introducing it ("new") and every later
+/// reference counts as "shared". On-disk size is read once, when a chunk is first
+/// seen, to estimate physical bytes.
+fn stats(store: &dyn ObjectStore, snapshot_paths: &[PathBuf]) -> anyhow::Result<()> {
+    if snapshot_paths.is_empty() {
+        return Err(anyhow::anyhow!("stats requires at least one snapshot file"));
+    }
+
+    // hash -> on-disk size (None if the chunk is absent from the store).
+    let mut seen: std::collections::HashMap<String, Option<u64>> = std::collections::HashMap::new();
+    let mut total_logical: u64 = 0;
+    let mut total_refs: u64 = 0;
+
+    for path in snapshot_paths {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read snapshot {}: {}", path.display(), e))?;
+        let snap: SnapshotMetadata = serde_json::from_str(&raw)
+            .map_err(|e| anyhow::anyhow!("failed to parse snapshot {}: {}", path.display(), e))?;
+
+        let mut new_chunks: u64 = 0;
+        let mut shared_chunks: u64 = 0;
+        for fe in &snap.files {
+            total_logical += fe.size;
+            for ch in &fe.chunk_hashes {
+                total_refs += 1;
+                if seen.contains_key(ch) {
+                    shared_chunks += 1;
+                } else {
+                    new_chunks += 1;
+                    let size = store.physical_size(ch);
+                    seen.insert(ch.clone(), size);
+                }
+            }
+        }
+        println!("{}: {} refs ({} new, {} shared)",
+            snap.id, new_chunks + shared_chunks, new_chunks, shared_chunks);
+    }
+
+    let unique = seen.len() as u64;
+    let present_unique = seen.values().filter(|s| s.is_some()).count() as u64;
+    let physical: u64 = seen.values().filter_map(|s| *s).sum();
+
+    println!("---");
+    println!("Snapshots analyzed: {}", snapshot_paths.len());
+    println!("Total logical bytes: {}", total_logical);
+    println!("Total chunk references: {}", total_refs);
+    println!("Unique chunks: {} ({} present on disk)", unique, present_unique);
+    let qualifier = if store.physical_size_is_exact() {
+        ""
+    } else {
+        " (logical estimate; pack is compressed)"
+    };
+    println!("Estimated physical bytes: {}{}", physical, qualifier);
+    if unique > 0 {
+        println!("Reference ratio: {:.2}x", total_refs as f64 / unique as f64);
+    }
+    if physical > 0 {
+        println!("Dedup ratio (logical/physical): {:.2}x", total_logical as f64 / physical as f64);
+    }
+
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let file = File::open(&args.snapshot)
-        .map_err(|e| anyhow::anyhow!("failed to open snapshot file {}: {}", args.snapshot.display(), e))?;
+    if let Some(Command::Stats { snapshots }) = &args.command {
+        let store = open_object_store(&args.objects)?;
+        return stats(store.as_ref(), snapshots);
+    }
+
+    let snapshot = args.snapshot.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("--snapshot is required"))?;
+    let file = File::open(snapshot)
+        .map_err(|e| anyhow::anyhow!("failed to open snapshot file {}: {}", snapshot.display(), e))?;
     let mut reader = BufReader::new(file);
     let mut raw = String::new();
     reader.read_to_string(&mut raw)?;
@@ -165,25 +977,19 @@ fn main() -> anyhow::Result<()> {
     let total_size: u64 = snap.files.iter().map(|f| f.size).sum();
     println!("Total declared byte size: {}", total_size);
 
-    let signer_pub_b64 = args.pubkey.as_ref().map(|s| s.as_str()).unwrap_or(&snap.signer_pub);
-    let signer_pub_bytes = general_purpose::STANDARD.decode(signer_pub_b64)
-        .map_err(|e| anyhow::anyhow!("failed to decode signer_pub base64: {}", e))?;
-    let public_key = PublicKey::from_bytes(&signer_pub_bytes)
-        .map_err(|e| anyhow::anyhow!("invalid ed25519 public key: {}", e))?;
+    let authorized = AuthorizedKeys::load(args.pubkey.as_deref(), args.authorized_keys.as_deref())?;
+    verify_signatures(&snap, args.pubkey.as_deref(), &authorized)?;
 
-    let signature_bytes = general_purpose::STANDARD.decode(&snap.signature)
-        .map_err(|e| anyhow::anyhow!("failed to decode signature base64: {}", e))?;
-    let signature = Signature::from_bytes(&signature_bytes)
-        .map_err(|e| anyhow::anyhow!("invalid signature format: {}", e))?;
+    check_freshness(&snap, args.max_age.as_deref())?;
 
-    let canonical = canonical_snapshot_bytes(&snap);
+    if let Some(ref dir) = args.chain {
+        verify_chain(dir, &snap)?;
+    }
 
-    match public_key.verify(&canonical, &signature) {
-        Ok(_) => println!("Signature: valid"),
-        Err(e) => {
-            println!("Signature: INVALID ({})", e);
-            return Err(anyhow::anyhow!("signature verification failed"));
-        }
+    let store = open_object_store(&args.objects)?;
+
+    if let Some(Command::Restore { out }) = &args.command {
+        return restore(&snap, store.as_ref(), out, args.hash_algo.as_deref());
     }
 
     // Collect all chunk hashes
@@ -195,11 +1001,18 @@ fn main() -> anyhow::Result<()> {
     }
     println!("Unique chunks referenced: {}", all_chunks.len());
 
-    // Check presence
+    // Check presence, and optionally content integrity.
     let mut missing = Vec::new();
+    let mut corrupted = Vec::new();
     for ch in &all_chunks {
-        if !chunk_exists(&args.objects, ch) {
+        if !store.exists(ch) {
             missing.push(ch.clone());
+        } else if args.verify_content {
+            let algo = HashAlgo::resolve(args.hash_algo.as_deref(), ch)?;
+            let digest = hash_reader(store.chunk_reader(ch)?, algo, None)?;
+            if !digest.eq_ignore_ascii_case(ch) {
+                corrupted.push(ch.clone());
+            }
         }
     }
 
@@ -215,5 +1028,105 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    if args.verify_content {
+        if corrupted.is_empty() {
+            println!("All present chunks match their referenced hashes.");
+        } else {
+            println!("Corrupted chunks: {} (showing up to {})", corrupted.len(), args.show_missing);
+            for ch in corrupted.iter().take(args.show_missing) {
+                println!("  {}", ch);
+            }
+            if corrupted.len() > args.show_missing {
+                println!("  ... and {} more", corrupted.len() - args.show_missing);
+            }
+        }
+    }
+
+    if args.verify_content && (!missing.is_empty() || !corrupted.is_empty()) {
+        return Err(anyhow::anyhow!(
+            "{} missing, {} corrupted chunk(s)", missing.len(), corrupted.len()));
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(id: &str, ts: &str, version: Option<u64>) -> ChainView {
+        ChainView { id: id.to_string(), parent: None, timestamp: ts.to_string(), version }
+    }
+
+    #[test]
+    fn sanitize_accepts_nested_relative_path() {
+        let rel = sanitize_rel_path("etc/config/app.toml").unwrap();
+        assert_eq!(rel, PathBuf::from("etc/config/app.toml"));
+    }
+
+    #[test]
+    fn sanitize_rejects_absolute_and_traversal() {
+        assert!(sanitize_rel_path("/etc/passwd").is_err());
+        assert!(sanitize_rel_path("../../etc/passwd").is_err());
+        assert!(sanitize_rel_path("a/../../b").is_err());
+        assert!(sanitize_rel_path("\\\\server\\share").is_err());
+    }
+
+    #[test]
+    fn sanitize_rejects_reserved_names_and_control_chars() {
+        assert!(sanitize_rel_path("CON").is_err());
+        assert!(sanitize_rel_path("dir/con.txt").is_err());
+        assert!(sanitize_rel_path("LPT9").is_err());
+        assert!(sanitize_rel_path("a/b\u{7}c").is_err());
+    }
+
+    #[test]
+    fn threshold_dedup_counts_distinct_valid_authorized() {
+        let checks = vec![
+            SignerCheck { key_id: "alice".into(), pub_bytes: vec![1], valid: true, authorized: true },
+            SignerCheck { key_id: "bob".into(), pub_bytes: vec![2], valid: true, authorized: true },
+            // same key under a second id — must not count twice
+            SignerCheck { key_id: "alice-2".into(), pub_bytes: vec![1], valid: true, authorized: true },
+            // valid but not in the trust anchor
+            SignerCheck { key_id: "mallory".into(), pub_bytes: vec![9], valid: true, authorized: false },
+            // authorized but signature invalid
+            SignerCheck { key_id: "carol".into(), pub_bytes: vec![3], valid: false, authorized: true },
+        ];
+        let (accepted, rejected) = count_distinct_authorized(&checks);
+        assert_eq!(accepted, vec!["alice".to_string(), "bob".to_string()]);
+        assert_eq!(rejected, vec!["alice-2".to_string(), "mallory".to_string(), "carol".to_string()]);
+    }
+
+    #[test]
+    fn authorized_keys_honor_role_binding() {
+        let keys = AuthorizedKeys { entries: vec![(Some("root".into()), vec![1, 2, 3])] };
+        assert!(keys.authorizes("root", &[1, 2, 3]));
+        // right key, wrong role
+        assert!(!keys.authorizes("backup", &[1, 2, 3]));
+        // wrong key
+        assert!(!keys.authorizes("root", &[9]));
+    }
+
+    #[test]
+    fn chain_link_requires_strictly_newer_timestamp() {
+        let child = link("b", "2024-02-01T00:00:00Z", None);
+        let parent = link("a", "2024-01-01T00:00:00Z", None);
+        assert!(check_chain_link(&child, &parent).is_ok());
+
+        let equal_parent = link("a", "2024-02-01T00:00:00Z", None);
+        assert!(check_chain_link(&child, &equal_parent).is_err());
+
+        let newer_parent = link("a", "2024-03-01T00:00:00Z", None);
+        assert!(check_chain_link(&child, &newer_parent).is_err());
+    }
+
+    #[test]
+    fn chain_link_requires_non_decreasing_version() {
+        let child = link("b", "2024-02-01T00:00:00Z", Some(1));
+        let parent = link("a", "2024-01-01T00:00:00Z", Some(2));
+        assert!(check_chain_link(&child, &parent).is_err());
+
+        let ok_parent = link("a", "2024-01-01T00:00:00Z", Some(1));
+        assert!(check_chain_link(&child, &ok_parent).is_ok());
+    }
+}